@@ -2,6 +2,8 @@ use std::{path::PathBuf, time::Duration};
 
 use clap::Parser;
 
+use crate::process_utils::ProcessState;
+
 /// Process memory watcher.
 ///
 /// Kills a process when it exceeds the given memory threshold.
@@ -14,8 +16,8 @@ pub struct Args {
     /// 'Resident Set Size' limit (in bytes, not in pages!).
     pub threshold: u64,
 
-    /// When a SIGKILL signal is sent wait for the specified timeout for the
-    /// process to terminate.
+    /// After sending SIGTERM, wait this long for the process to terminate
+    /// before escalating to SIGKILL.
     #[clap(long, short, value_parser = humantime::parse_duration, default_value = "60s")]
     pub timeout: Duration,
 
@@ -27,6 +29,52 @@ pub struct Args {
     #[clap(long)]
     pub check: bool,
 
+    /// Turns the tool into a long-running daemon, re-checking the process
+    /// every given interval instead of exiting after a single pass.
+    #[clap(long, value_parser = humantime::parse_duration)]
+    pub watch: Option<Duration>,
+
+    /// Match by the resolved `/proc/[pid]/exe` path basename instead of
+    /// `comm`. Conflicts with `--match-cmdline`.
+    #[clap(long, conflicts_with = "match_cmdline")]
+    pub match_exe: bool,
+
+    /// Match by testing this regex against the space-joined
+    /// `/proc/[pid]/cmdline` instead of `comm`. Conflicts with `--match-exe`.
+    #[clap(long, conflicts_with = "match_exe")]
+    pub match_cmdline: Option<String>,
+
+    /// Append the relaunched process's stdout to this file.
+    #[clap(long)]
+    pub stdout: Option<PathBuf>,
+
+    /// Append the relaunched process's stderr to this file.
+    #[clap(long)]
+    pub stderr: Option<PathBuf>,
+
+    /// Write the PID of the relaunched process to this file.
+    #[clap(long)]
+    pub pid_file: Option<PathBuf>,
+
+    /// Virtual memory size limit (in bytes).
+    #[clap(long)]
+    pub vsize_threshold: Option<u64>,
+
+    /// CPU usage limit, as a percentage of a single core, sampled across
+    /// `--watch` iterations.
+    #[clap(long)]
+    pub cpu_threshold: Option<f64>,
+
+    /// Open file descriptor count limit.
+    #[clap(long)]
+    pub fd_threshold: Option<usize>,
+
+    /// Process states that are normally excluded from matching (`zombie`,
+    /// `dead`) to opt back in, comma-separated (e.g.
+    /// `--include-states zombie,dead`).
+    #[clap(long, value_delimiter = ',')]
+    pub include_states: Vec<ProcessState>,
+
     /// Command to launch.
     #[clap(long, short)]
     pub command: String,
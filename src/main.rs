@@ -7,7 +7,7 @@ use std::{
     ffi::{OsStr, OsString},
     path::PathBuf,
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 mod cmdargs;
@@ -16,7 +16,10 @@ mod process_utils;
 use clap::Parser;
 use display_error_chain::DisplayErrorChain;
 use libc::pid_t;
-use process_utils::{find_processes, launch_process, ProcessInfo};
+use process_utils::{
+    launch_process, LaunchOptions, ProcessBackend, ProcessInfo, ProcessMatcher, ProcessState,
+};
+use regex::Regex;
 use snafu::{OptionExt, ResultExt, Snafu};
 
 #[derive(Debug, Snafu)]
@@ -30,11 +33,14 @@ enum CheckError {
 }
 
 fn check_process<I, S>(
-    program_name: &str,
+    backend: &dyn ProcessBackend,
+    matcher: &ProcessMatcher,
+    include_states: &[ProcessState],
     pid: pid_t,
     cmd: &str,
     args: I,
     env: HashMap<OsString, OsString>,
+    launch_options: &LaunchOptions,
 ) -> Result<(), CheckError>
 where
     I: IntoIterator<Item = S>,
@@ -42,7 +48,9 @@ where
 {
     thread::sleep(Duration::from_secs(5));
     log::trace!["Checking if the process is running after the launch."];
-    let processes = find_processes(program_name).context(CheckFindProcessesSnafu)?;
+    let processes = backend
+        .find_processes(matcher, include_states)
+        .context(CheckFindProcessesSnafu)?;
     if processes
         .into_iter()
         .any(|proc_info| proc_info.pid() == pid)
@@ -50,7 +58,7 @@ where
         log::trace!["Ok, the process is running."];
     } else {
         log::warn!["Recently launched process not found :( Let's try to start it again."];
-        launch_process(cmd, args, env).context(CheckLaunchProcessSnafu)?;
+        launch_process(cmd, args, env, launch_options).context(CheckLaunchProcessSnafu)?;
     }
     Ok(())
 }
@@ -63,6 +71,15 @@ enum RunError {
         source: anyhow::Error,
     },
 
+    #[snafu(display("Invalid --match-cmdline regex"))]
+    InvalidMatchCmdline { source: regex::Error },
+
+    #[snafu(display("{flag} isn't supported by this platform's process backend"))]
+    UnsupportedThreshold { flag: &'static str },
+
+    #[snafu(display("{flag} requires --watch: CPU usage can't be computed from a single sample"))]
+    ThresholdNeedsWatch { flag: &'static str },
+
     #[snafu(display("Can't find a process"))]
     FindProcess {
         source: process_utils::FindProcessError,
@@ -81,6 +98,109 @@ enum RunError {
     Check { source: CheckError },
 }
 
+/// Memory/CPU/FD limits a watched process is compared against.
+#[derive(Debug, Clone, Copy)]
+struct Thresholds {
+    rss: u64,
+    vsize: Option<u64>,
+    cpu_percent: Option<f64>,
+    fd_count: Option<usize>,
+}
+
+/// Checks `process` against `thresholds`, returning a description of the
+/// first metric that was exceeded, if any.
+///
+/// `previous` is an earlier sample of the same process together with the
+/// actual wall-clock time elapsed since it was taken (if any), used to
+/// compute CPU usage.
+fn exceeded_threshold(
+    process: &ProcessInfo,
+    thresholds: &Thresholds,
+    previous: Option<(&ProcessInfo, Duration)>,
+) -> Option<String> {
+    let memory = process.rss();
+    if memory > thresholds.rss {
+        return Some(format!("RSS {memory} > {}", thresholds.rss));
+    }
+    if let Some(vsize_threshold) = thresholds.vsize {
+        let vsize = process.vsize();
+        if vsize > vsize_threshold {
+            return Some(format!("virtual memory size {vsize} > {vsize_threshold}"));
+        }
+    }
+    if let Some(fd_threshold) = thresholds.fd_count {
+        let fd_count = process.fd_count();
+        if fd_count > fd_threshold {
+            return Some(format!(
+                "open file descriptor count {fd_count} > {fd_threshold}"
+            ));
+        }
+    }
+    if let (Some(cpu_threshold), Some((previous, elapsed))) = (thresholds.cpu_percent, previous) {
+        if let Some(cpu_percent) = process.cpu_percent_since(previous, elapsed) {
+            log::info!["CPU: {cpu_percent:.1}%"];
+            if cpu_percent > cpu_threshold {
+                return Some(format!("CPU usage {cpu_percent:.1}% > {cpu_threshold:.1}%"));
+            }
+        }
+    }
+    None
+}
+
+/// Runs a single scan-and-maybe-restart pass.
+///
+/// Returns the sampled [`ProcessInfo`] so the caller can feed it back in as
+/// `previous` on the next pass (for CPU usage tracking), or `None` if the
+/// process was restarted.
+fn run_once(
+    backend: &dyn ProcessBackend,
+    matcher: &ProcessMatcher,
+    include_states: &[ProcessState],
+    thresholds: &Thresholds,
+    previous: Option<(&ProcessInfo, Duration)>,
+    timeout: Duration,
+    should_check_process: bool,
+    cmd: &str,
+    cmd_args: &[String],
+    launch_options: &LaunchOptions,
+) -> Result<Option<ProcessInfo>, RunError> {
+    let processes = backend
+        .find_processes(matcher, include_states)
+        .context(FindProcessSnafu)?;
+    if processes.len() > 1 {
+        return MultipleFoundSnafu { processes }.fail();
+    }
+
+    let process = processes.into_iter().next().context(ProcessNotFoundSnafu)?;
+    log::info!["Memory: {} kilobytes", process.rss() as f64 / 1024.];
+
+    match exceeded_threshold(&process, thresholds, previous) {
+        Some(reason) => {
+            log::warn!["Threshold exceeded: {reason}"];
+            let env = process.env().clone();
+            let pid = process
+                .restart_process(backend, timeout, cmd, cmd_args, launch_options)
+                .context(RestartSnafu)?;
+
+            if should_check_process {
+                check_process(
+                    backend,
+                    matcher,
+                    include_states,
+                    pid,
+                    cmd,
+                    cmd_args.to_owned(),
+                    env,
+                    launch_options,
+                )
+                .context(CheckSnafu)?;
+            }
+            Ok(None)
+        }
+        None => Ok(Some(process)),
+    }
+}
+
 fn run() -> Result<(), RunError> {
     let cmdargs::Args {
         name: program_name,
@@ -88,6 +208,16 @@ fn run() -> Result<(), RunError> {
         timeout,
         log_config,
         check: should_check_process,
+        watch,
+        match_exe,
+        match_cmdline,
+        stdout,
+        stderr,
+        pid_file,
+        vsize_threshold,
+        cpu_threshold,
+        fd_threshold,
+        include_states,
         command: cmd,
         args: cmd_args,
     } = cmdargs::Args::parse();
@@ -95,27 +225,95 @@ fn run() -> Result<(), RunError> {
     log4rs::init_file(&log_config, Default::default())
         .context(InitLogsSnafu { path: log_config })?;
 
-    let processes = find_processes(&program_name).context(FindProcessSnafu)?;
-    if processes.len() > 1 {
-        return MultipleFoundSnafu { processes }.fail();
-    }
+    let matcher = if let Some(pattern) = match_cmdline {
+        ProcessMatcher::Cmdline(Regex::new(&pattern).context(InvalidMatchCmdlineSnafu)?)
+    } else if match_exe {
+        ProcessMatcher::Exe(program_name)
+    } else {
+        ProcessMatcher::Comm(program_name)
+    };
 
-    let process = processes.into_iter().next().context(ProcessNotFoundSnafu)?;
+    let launch_options = LaunchOptions {
+        stdout,
+        stderr,
+        pid_file,
+    };
 
-    let memory = process.rss();
-    log::info!["Memory: {} kilobytes", memory as f64 / 1024.];
-    if memory > threshold {
-        log::warn!["Threshold exceeded: {} > {}", memory, threshold];
-        let env = process.env().clone();
-        let pid = process
-            .restart_process(timeout, &cmd, &cmd_args)
-            .context(RestartSnafu)?;
-
-        if should_check_process {
-            check_process(&program_name, pid, &cmd, cmd_args, env).context(CheckSnafu)?;
+    let thresholds = Thresholds {
+        rss: threshold,
+        vsize: vsize_threshold,
+        cpu_percent: cpu_threshold,
+        fd_count: fd_threshold,
+    };
+
+    let backend = process_utils::default_backend();
+
+    if thresholds.cpu_percent.is_some() && !backend.supports_cpu_threshold() {
+        return UnsupportedThresholdSnafu {
+            flag: "--cpu-threshold",
+        }
+        .fail();
+    }
+    if thresholds.fd_count.is_some() && !backend.supports_fd_threshold() {
+        return UnsupportedThresholdSnafu {
+            flag: "--fd-threshold",
+        }
+        .fail();
+    }
+    if thresholds.cpu_percent.is_some() && watch.is_none() {
+        return ThresholdNeedsWatchSnafu {
+            flag: "--cpu-threshold",
+        }
+        .fail();
+    }
+
+    match watch {
+        None => run_once(
+            backend.as_ref(),
+            &matcher,
+            &include_states,
+            &thresholds,
+            None,
+            timeout,
+            should_check_process,
+            &cmd,
+            &cmd_args,
+            &launch_options,
+        )
+        .map(|_| ()),
+        Some(interval) => {
+            let mut previous: Option<(ProcessInfo, Instant)> = None;
+            loop {
+                let now = Instant::now();
+                match run_once(
+                    backend.as_ref(),
+                    &matcher,
+                    &include_states,
+                    &thresholds,
+                    previous
+                        .as_ref()
+                        .map(|(info, at)| (info, now.duration_since(*at))),
+                    timeout,
+                    should_check_process,
+                    &cmd,
+                    &cmd_args,
+                    &launch_options,
+                ) {
+                    Ok(sample) => previous = sample.map(|info| (info, now)),
+                    Err(e @ RunError::MultipleFound { .. })
+                    | Err(e @ RunError::ProcessNotFound) => {
+                        log::warn![
+                            "{}; will retry on the next pass",
+                            DisplayErrorChain::new(&e)
+                        ];
+                        previous = None;
+                    }
+                    Err(e) => return Err(e),
+                }
+                thread::sleep(interval);
+            }
         }
     }
-    Ok(())
 }
 
 fn main() {
@@ -0,0 +1,218 @@
+//! The Linux [`ProcessBackend`], backed by `procfs`.
+
+use std::{ffi::OsStr, time::Duration};
+
+use libc::{self, c_int, pid_t};
+use procfs::ProcError;
+use snafu::{ResultExt, Snafu};
+
+use super::{
+    state_included, InvalidSignalSnafu, KillError, LinuxSnafu, NotFoundSnafu,
+    PermissionDeniedSnafu, ProcessBackend, ProcessInfo, ProcessMatcher, ProcessState,
+};
+
+/// Errors specific to the Linux `procfs`-based backend.
+#[derive(Debug, Snafu)]
+pub enum FindProcessError {
+    #[snafu(display("Unable to get a list of processes"))]
+    GetProcessList { source: ProcError },
+
+    #[snafu(display("Unable to fetch next process info"))]
+    GetProcess { source: ProcError },
+
+    #[snafu(display("Can't get environment variables of a process #{pid}"))]
+    GetEnv { source: ProcError, pid: pid_t },
+
+    #[snafu(display("Can't get stats of a process #{pid}"))]
+    GetStat { source: ProcError, pid: pid_t },
+
+    #[snafu(display("Can't calculate RSS size in bytes of a process #{pid}"))]
+    RssBytes { source: ProcError, pid: pid_t },
+
+    #[snafu(display("Can't get the executable path of a process #{pid}"))]
+    GetExe { source: ProcError, pid: pid_t },
+
+    #[snafu(display("Can't get the command line of a process #{pid}"))]
+    GetCmdline { source: ProcError, pid: pid_t },
+
+    #[snafu(display("Can't get the open file descriptor count of a process #{pid}"))]
+    GetFdCount { source: ProcError, pid: pid_t },
+
+    #[snafu(display("Can't look up process #{pid}"))]
+    GetProcessByPid { source: ProcError, pid: pid_t },
+}
+
+/// Converts a number of clock ticks (as reported by `/proc/[pid]/stat`) into
+/// a [`Duration`], using the kernel's `_SC_CLK_TCK` clock resolution.
+fn ticks_to_duration(ticks: u64) -> Duration {
+    let ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) } as u64;
+    Duration::from_secs_f64(ticks as f64 / ticks_per_second as f64)
+}
+
+/// Parses the `state` char from `/proc/[pid]/stat` (man 5 proc).
+fn parse_state(state: char) -> ProcessState {
+    match state {
+        'R' => ProcessState::Run,
+        'S' => ProcessState::Sleep,
+        'D' => ProcessState::Disk,
+        'T' | 't' => ProcessState::Stop,
+        'Z' => ProcessState::Zombie,
+        'X' | 'x' => ProcessState::Dead,
+        'I' => ProcessState::Idle,
+        _ => ProcessState::Unknown,
+    }
+}
+
+/// Decides whether `process` matches `matcher`, reading only the fields the
+/// given matcher variant actually needs.
+///
+/// `/proc/[pid]/exe` and `/cmdline` are restricted to the process's owner
+/// (or `CAP_SYS_PTRACE`), unlike `/proc/[pid]/stat`. A `PermissionDenied`
+/// while probing another user's process is treated as "doesn't match"
+/// rather than a fatal error, otherwise an unprivileged watcher using
+/// `--match-exe`/`--match-cmdline` couldn't scan past the first
+/// root-owned process (init/systemd/kthreadd always exist).
+fn quick_matches(
+    process: &procfs::process::Process,
+    stat: &procfs::process::Stat,
+    matcher: &ProcessMatcher,
+) -> Result<bool, FindProcessError> {
+    let pid = process.pid();
+    match matcher {
+        ProcessMatcher::Comm(name) => Ok(&stat.comm == name),
+        ProcessMatcher::Exe(name) => match process.exe() {
+            Ok(exe) => Ok(exe.file_name().and_then(OsStr::to_str) == Some(name.as_str())),
+            Err(ProcError::PermissionDenied(_)) => Ok(false),
+            Err(source) => Err(source).context(GetExeSnafu { pid }),
+        },
+        ProcessMatcher::Cmdline(regex) => match process.cmdline() {
+            Ok(cmdline) => Ok(regex.is_match(&cmdline.join(" "))),
+            Err(ProcError::PermissionDenied(_)) => Ok(false),
+            Err(source) => Err(source).context(GetCmdlineSnafu { pid }),
+        },
+    }
+}
+
+/// Builds a [`ProcessInfo`] out of a `procfs` process handle that has
+/// already been matched, reading the remaining (owner-restricted) fields
+/// unconditionally.
+fn process_info(
+    process: procfs::process::Process,
+    stat: procfs::process::Stat,
+) -> Result<ProcessInfo, FindProcessError> {
+    let pid = process.pid();
+
+    let rss = stat.rss_bytes().context(RssBytesSnafu { pid })?;
+    let exe = process.exe().context(GetExeSnafu { pid })?;
+    let cmdline = process.cmdline().context(GetCmdlineSnafu { pid })?;
+    let fd_count = process.fd_count().context(GetFdCountSnafu { pid })?;
+    let command = stat.comm.clone();
+
+    Ok(ProcessInfo {
+        pid,
+        env: process.environ().context(GetEnvSnafu { pid })?,
+        rss,
+        start_time: stat.starttime,
+        command,
+        exe,
+        cmdline,
+        vsize: stat.vsize,
+        fd_count,
+        thread_count: stat.num_threads,
+        cpu_time: ticks_to_duration(stat.utime + stat.stime),
+        state: parse_state(stat.state),
+    })
+}
+
+/// Matches and, if matched, fully reads a single process from a scan.
+///
+/// Returns `Ok(None)` for processes that don't match, that exited before
+/// their `stat` could be read, whose restricted fields we can't read while
+/// matching (see [`quick_matches`]), or whose state is excluded. Once a
+/// process has matched, any failure to read its remaining fields in
+/// [`process_info`] is returned as an error and aborts the scan.
+fn scan_one(
+    process: Result<procfs::process::Process, ProcError>,
+    matcher: &ProcessMatcher,
+    include_states: &[ProcessState],
+) -> Result<Option<ProcessInfo>, FindProcessError> {
+    let process = process.context(GetProcessSnafu)?;
+    let pid = process.pid();
+
+    // `/proc/[pid]/stat` is world-readable, so it's safe to read for every
+    // process on the system.
+    let stat = match process.stat() {
+        Ok(stat) => stat,
+        Err(ProcError::NotFound(_)) => return Ok(None),
+        Err(source) => return Err(source).context(GetStatSnafu { pid }),
+    };
+
+    if !quick_matches(&process, &stat, matcher)? {
+        return Ok(None);
+    }
+
+    // Decide on state-based exclusion before reading the owner-restricted
+    // fields in `process_info`: a zombie/dead process has already lost
+    // `/proc/[pid]/exe`, `/cmdline` etc, so calling `process_info` on one
+    // would turn a process we're about to discard anyway into a fatal
+    // `GetExe`/`GetCmdline` error instead of being silently skipped.
+    let state = parse_state(stat.state);
+    if !state_included(state, include_states) {
+        return Ok(None);
+    }
+
+    let info = process_info(process, stat)?;
+    log::trace!("Found process #{pid} (state: {})", info.state);
+    Ok(Some(info))
+}
+
+fn process_by_pid_inner(pid: pid_t) -> Result<Option<ProcessInfo>, FindProcessError> {
+    let process = match procfs::process::Process::new(pid) {
+        Ok(process) => process,
+        Err(ProcError::NotFound(_)) => return Ok(None),
+        Err(source) => return Err(source).context(GetProcessByPidSnafu { pid }),
+    };
+    let stat = process.stat().context(GetStatSnafu { pid })?;
+    process_info(process, stat).map(Some)
+}
+
+/// The Linux `procfs`-based [`ProcessBackend`].
+#[derive(Debug, Default)]
+pub struct LinuxBackend;
+
+impl ProcessBackend for LinuxBackend {
+    fn find_processes(
+        &self,
+        matcher: &ProcessMatcher,
+        include_states: &[ProcessState],
+    ) -> Result<Vec<ProcessInfo>, super::FindProcessError> {
+        let processes: Result<Vec<ProcessInfo>, FindProcessError> =
+            procfs::process::all_processes()
+                .context(GetProcessListSnafu)?
+                .map(|process| scan_one(process, matcher, include_states))
+                .filter_map(Result::transpose)
+                .collect();
+        processes.context(LinuxSnafu)
+    }
+
+    fn process_by_pid(&self, pid: pid_t) -> Result<Option<ProcessInfo>, super::FindProcessError> {
+        process_by_pid_inner(pid).context(LinuxSnafu)
+    }
+
+    fn send_signal(&self, pid: pid_t, signal: c_int) -> Result<(), KillError> {
+        log::trace!["Sending signal {} to process {}", signal, pid];
+        match unsafe { libc::kill(pid, signal) } {
+            0 => Ok(()),
+            -1 => {
+                let errno: c_int = unsafe { *libc::__errno_location() };
+                match errno {
+                    libc::EINVAL => InvalidSignalSnafu { signal }.fail(),
+                    libc::EPERM => PermissionDeniedSnafu { pid, signal }.fail(),
+                    libc::ESRCH => NotFoundSnafu { pid }.fail(),
+                    x => unreachable!["Unexpected error value {x}"],
+                }
+            }
+            x => panic!["Unexpected return code {x}"],
+        }
+    }
+}
@@ -0,0 +1,537 @@
+//! Process discovery, signalling and launching, abstracted behind a
+//! [`ProcessBackend`] so the watcher isn't tied to Linux's `procfs`.
+
+mod linux;
+#[cfg(any(target_os = "freebsd", target_os = "macos"))]
+mod sysinfo_backend;
+
+use std::{
+    collections::HashMap,
+    ffi::{OsStr, OsString},
+    path::PathBuf,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use libc::{c_int, pid_t};
+use regex::Regex;
+use snafu::{ResultExt, Snafu};
+
+pub use linux::LinuxBackend;
+#[cfg(any(target_os = "freebsd", target_os = "macos"))]
+pub use sysinfo_backend::SysinfoBackend;
+
+/// Returns the [`ProcessBackend`] appropriate for the platform this binary
+/// was built for.
+pub fn default_backend() -> Box<dyn ProcessBackend> {
+    #[cfg(any(target_os = "freebsd", target_os = "macos"))]
+    {
+        Box::new(SysinfoBackend::new())
+    }
+    #[cfg(not(any(target_os = "freebsd", target_os = "macos")))]
+    {
+        Box::new(LinuxBackend)
+    }
+}
+
+/// Information on running program.
+#[derive(Debug)]
+pub struct ProcessInfo {
+    /// Process ID.
+    pub(crate) pid: pid_t,
+
+    /// 'Resident Set Size' in bytes.
+    pub(crate) rss: u64,
+
+    /// Environment variables.
+    pub(crate) env: HashMap<OsString, OsString>,
+
+    /// The time the process started, in a backend-specific unit (clock
+    /// ticks after system boot on Linux, seconds since the Unix epoch with
+    /// `sysinfo`).
+    pub(crate) start_time: u64,
+
+    /// The process name (`comm` on Linux, truncated to 15 characters).
+    pub(crate) command: String,
+
+    /// Resolved path of the process's executable.
+    pub(crate) exe: PathBuf,
+
+    /// The process's command line.
+    pub(crate) cmdline: Vec<String>,
+
+    /// Virtual memory size in bytes.
+    pub(crate) vsize: u64,
+
+    /// Number of open file descriptors (`0` if the backend can't report it).
+    pub(crate) fd_count: usize,
+
+    /// Number of threads (`0` if the backend can't report it).
+    pub(crate) thread_count: i64,
+
+    /// Total CPU time spent by the process so far (`0` if the backend can't
+    /// report it).
+    pub(crate) cpu_time: Duration,
+
+    /// The process's current run state.
+    pub(crate) state: ProcessState,
+}
+
+/// Strategy used to match a running process against the requested target.
+#[derive(Debug)]
+pub enum ProcessMatcher {
+    /// Match the process name (possibly truncated to 15 characters on
+    /// Linux, where it comes from `comm` in `/proc/[pid]/stat`).
+    Comm(String),
+
+    /// Match the basename of the resolved path to the process's executable.
+    Exe(String),
+
+    /// Match a regex against the space-joined command line.
+    Cmdline(Regex),
+}
+
+/// A process's run state, modeled on `sysinfo`'s `ProcessStatus` and the
+/// `state` char in `/proc/[pid]/stat` (man 5 proc).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessState {
+    /// Running, or runnable and waiting on the run queue.
+    Run,
+    /// Sleeping in an interruptible wait.
+    Sleep,
+    /// Waiting in uninterruptible disk sleep.
+    Disk,
+    /// Stopped, by job control signal or while being traced.
+    Stop,
+    /// Terminated but not yet reaped by its parent.
+    Zombie,
+    /// Dead (should never actually be observed).
+    Dead,
+    /// Idle kernel thread.
+    Idle,
+    /// Any other, unrecognized state.
+    Unknown,
+}
+
+impl std::fmt::Display for ProcessState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Run => "running",
+            Self::Sleep => "sleeping",
+            Self::Disk => "disk sleep",
+            Self::Stop => "stopped",
+            Self::Zombie => "zombie",
+            Self::Dead => "dead",
+            Self::Idle => "idle",
+            Self::Unknown => "unknown",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl std::str::FromStr for ProcessState {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "run" | "running" => Ok(Self::Run),
+            "sleep" | "sleeping" => Ok(Self::Sleep),
+            "disk" => Ok(Self::Disk),
+            "stop" | "stopped" => Ok(Self::Stop),
+            "zombie" => Ok(Self::Zombie),
+            "dead" => Ok(Self::Dead),
+            "idle" => Ok(Self::Idle),
+            "unknown" => Ok(Self::Unknown),
+            other => Err(format!("unrecognized process state {other:?}")),
+        }
+    }
+}
+
+/// Whether a process in `state` should be matched, given the states opted
+/// back in via `--include-states`.
+///
+/// Zombies and dead processes are excluded by default: restarting against
+/// one wastes a signal and can misfire PID-reuse detection.
+pub(crate) fn state_included(state: ProcessState, include_states: &[ProcessState]) -> bool {
+    match state {
+        ProcessState::Zombie | ProcessState::Dead => include_states.contains(&state),
+        _ => true,
+    }
+}
+
+/// An error encountered while sending a signal to a process.
+#[derive(Debug, Snafu)]
+pub enum KillError {
+    #[snafu(display("Invalid signal detected"))]
+    InvalidSignal { signal: c_int },
+    #[snafu(display("Permission denied to send a signal {signal} to process #{pid}"))]
+    PermissionDenied { signal: c_int, pid: pid_t },
+    #[snafu(display("Process #{pid} not found"))]
+    NotFound { pid: pid_t },
+}
+
+/// An error encountered while listing or looking up processes.
+#[derive(Debug, Snafu)]
+pub enum FindProcessError {
+    #[snafu(display("Linux backend error"))]
+    Linux { source: linux::FindProcessError },
+
+    #[cfg(any(target_os = "freebsd", target_os = "macos"))]
+    #[snafu(display("sysinfo backend error"))]
+    Sysinfo {
+        source: sysinfo_backend::FindProcessError,
+    },
+}
+
+/// Finds and signals processes on the current platform.
+///
+/// Implemented once per supported platform: [`LinuxBackend`] uses `procfs`
+/// directly, while [`SysinfoBackend`] (on FreeBSD/macOS) delegates to the
+/// `sysinfo` crate.
+pub trait ProcessBackend {
+    /// Finds running processes matching the given [`ProcessMatcher`].
+    ///
+    /// Zombie/dead processes are excluded unless their state is listed in
+    /// `include_states`.
+    fn find_processes(
+        &self,
+        matcher: &ProcessMatcher,
+        include_states: &[ProcessState],
+    ) -> Result<Vec<ProcessInfo>, FindProcessError>;
+
+    /// Looks up a single process by its PID.
+    fn process_by_pid(&self, pid: pid_t) -> Result<Option<ProcessInfo>, FindProcessError>;
+
+    /// Sends a signal to a process.
+    fn send_signal(&self, pid: pid_t, signal: c_int) -> Result<(), KillError>;
+
+    /// Whether [`ProcessInfo::cpu_time`] is tracked by this backend, i.e.
+    /// whether `--cpu-threshold` can ever actually trip.
+    fn supports_cpu_threshold(&self) -> bool {
+        true
+    }
+
+    /// Whether [`ProcessInfo::fd_count`] is tracked by this backend, i.e.
+    /// whether `--fd-threshold` can ever actually trip.
+    fn supports_fd_threshold(&self) -> bool {
+        true
+    }
+}
+
+/// A high-level error.
+#[derive(Debug, Snafu)]
+pub enum WaitStopError {
+    #[snafu(display("Sending signal 0 to #{pid}"))]
+    SendSignal0 { pid: pid_t, source: KillError },
+
+    #[snafu(display("Can't get process information of #{pid}"))]
+    GetProcessInfo {
+        pid: pid_t,
+        source: FindProcessError,
+    },
+}
+
+/// Outcome of [`ProcessInfo::wait_stop`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum WaitOutcome {
+    /// The process has actually stopped.
+    Stopped,
+    /// The timeout has been reached while the process was still running.
+    TimedOut,
+}
+
+/// Options controlling a relaunched process's I/O redirection and PID file.
+#[derive(Debug, Default, Clone)]
+pub struct LaunchOptions {
+    /// Path to append the relaunched process's stdout to.
+    ///
+    /// When unset, stdout is discarded.
+    pub stdout: Option<PathBuf>,
+
+    /// Path to append the relaunched process's stderr to.
+    ///
+    /// When unset, stderr is discarded.
+    pub stderr: Option<PathBuf>,
+
+    /// Path to write the relaunched process's PID to.
+    pub pid_file: Option<PathBuf>,
+}
+
+fn open_append(path: &std::path::Path) -> Result<std::fs::File, std::io::Error> {
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+}
+
+#[derive(Debug, Snafu)]
+pub enum LaunchError {
+    #[snafu(display("Can't open the stdout file {}", path.display()))]
+    OpenStdout {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("Can't open the stderr file {}", path.display()))]
+    OpenStderr {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("Can't spawn the process"))]
+    Spawn { source: std::io::Error },
+
+    #[snafu(display("Can't write the pid file {}", path.display()))]
+    WritePidFile {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+}
+
+/// Launches and detaches a process.
+///
+/// Returns PID of the detached process.
+pub fn launch_process<I, S>(
+    cmd: &str,
+    args: I,
+    environment: HashMap<OsString, OsString>,
+    options: &LaunchOptions,
+) -> Result<pid_t, LaunchError>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    log::trace!["Launching '{cmd}'"];
+
+    let stdout = match &options.stdout {
+        Some(path) => Stdio::from(open_append(path).context(OpenStdoutSnafu { path })?),
+        None => Stdio::null(),
+    };
+    let stderr = match &options.stderr {
+        Some(path) => Stdio::from(open_append(path).context(OpenStderrSnafu { path })?),
+        None => Stdio::null(),
+    };
+
+    let child = Command::new(cmd)
+        .args(args)
+        .env_clear()
+        .envs(environment)
+        .stdout(stdout)
+        .stderr(stderr)
+        .spawn()
+        .context(SpawnSnafu)?;
+
+    let pid = child.id() as pid_t;
+
+    if let Some(path) = &options.pid_file {
+        std::fs::write(path, pid.to_string()).context(WritePidFileSnafu { path })?;
+    }
+
+    Ok(pid)
+}
+
+/// An error encountered during a process restart.
+#[derive(Debug, Snafu)]
+pub enum RestartError {
+    /// Unable to terminate process.
+    #[snafu(display("Unable to terminate process"))]
+    Terminate {
+        /// Source error.
+        source: KillError,
+    },
+
+    /// Wait for a process to stop.
+    #[snafu(display("Wait for a process to stop"))]
+    WaitStop {
+        /// Source error.
+        source: WaitStopError,
+    },
+
+    /// Unable to send a `SIGKILL` after a `SIGTERM` timeout.
+    #[snafu(display("Unable to kill the process"))]
+    Kill {
+        /// Source error.
+        source: KillError,
+    },
+
+    /// The process is still alive after the post-`SIGKILL` grace period.
+    #[snafu(display("Process #{pid} is still alive after SIGKILL"))]
+    StillAlive {
+        /// PID of the process that wouldn't die.
+        pid: pid_t,
+    },
+
+    /// Re-launch the process.
+    #[snafu(display("Re-launch the process"))]
+    LaunchProcess {
+        /// Source error.
+        source: LaunchError,
+    },
+}
+
+impl ProcessInfo {
+    /// Checks whether a given process has stopped.
+    fn has_stopped(&self, backend: &dyn ProcessBackend) -> Result<bool, WaitStopError> {
+        let pid = self.pid;
+        match backend.send_signal(pid, 0) {
+            Err(KillError::NotFound { pid }) => {
+                log::trace!["Process #{pid} not found"];
+                return Ok(true);
+            }
+            Err(e) => Err(e).context(SendSignal0Snafu { pid })?,
+            Ok(_) => {}
+        };
+        log::trace!["Process found. Let's check if its `start_time` is the same"];
+
+        let current_start_time = backend
+            .process_by_pid(pid)
+            .context(GetProcessInfoSnafu { pid })?
+            .map(|info| info.start_time);
+        Ok(current_start_time != Some(self.start_time))
+    }
+
+    /// Waits for a process to stop.
+    ///
+    /// Reports whether the process has actually stopped, or whether the
+    /// `timeout` has been reached first.
+    pub fn wait_stop(
+        &self,
+        backend: &dyn ProcessBackend,
+        timeout: Duration,
+    ) -> Result<WaitOutcome, WaitStopError> {
+        const INTERVAL: Duration = Duration::from_secs(1);
+
+        log::trace!["Waiting for the pid #{} to stop.", self.pid];
+        let started = Instant::now();
+        loop {
+            if self.has_stopped(backend)? {
+                log::trace!["Process #{} has stopped.", self.pid];
+                return Ok(WaitOutcome::Stopped);
+            }
+            if started.elapsed() > timeout {
+                log::trace!["Timeout has been reached, leaving the process as it is."];
+                return Ok(WaitOutcome::TimedOut);
+            }
+            thread::sleep(INTERVAL);
+        }
+    }
+
+    /// Restarts the given process.
+    ///
+    /// Sends `SIGTERM` and waits for the process to stop. If it's still
+    /// alive once `wait_timeout` elapses, escalates to `SIGKILL` and waits
+    /// a short additional grace period before giving up.
+    ///
+    /// Returns the PID of the detached process.
+    pub fn restart_process<I, S>(
+        self,
+        backend: &dyn ProcessBackend,
+        wait_timeout: Duration,
+        cmd: &str,
+        args: I,
+        launch_options: &LaunchOptions,
+    ) -> Result<pid_t, RestartError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        const KILL_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+        backend
+            .send_signal(self.pid, libc::SIGTERM)
+            .context(TerminateSnafu)?;
+        if self
+            .wait_stop(backend, wait_timeout)
+            .context(WaitStopSnafu)?
+            == WaitOutcome::TimedOut
+        {
+            log::warn![
+                "Process #{} ignored SIGTERM, escalating to SIGKILL.",
+                self.pid
+            ];
+            backend
+                .send_signal(self.pid, libc::SIGKILL)
+                .context(KillSnafu)?;
+            if self
+                .wait_stop(backend, KILL_GRACE_PERIOD)
+                .context(WaitStopSnafu)?
+                == WaitOutcome::TimedOut
+            {
+                return StillAliveSnafu { pid: self.pid }.fail();
+            }
+        }
+        launch_process(cmd, args, self.env, launch_options).context(LaunchProcessSnafu)
+    }
+
+    /// PID of the process.
+    pub fn pid(&self) -> i32 {
+        self.pid
+    }
+
+    /// Returns environment of the process.
+    pub fn env(&self) -> &HashMap<OsString, OsString> {
+        &self.env
+    }
+
+    /// Returns the process name (`comm` on Linux, truncated to 15
+    /// characters).
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    /// 'Resident Set Size' in bytes.
+    pub fn rss(&self) -> u64 {
+        self.rss
+    }
+
+    /// Resolved path of the process's executable.
+    pub fn exe(&self) -> &std::path::Path {
+        &self.exe
+    }
+
+    /// The process's command line.
+    pub fn cmdline(&self) -> &[String] {
+        &self.cmdline
+    }
+
+    /// Virtual memory size in bytes.
+    pub fn vsize(&self) -> u64 {
+        self.vsize
+    }
+
+    /// Number of open file descriptors.
+    pub fn fd_count(&self) -> usize {
+        self.fd_count
+    }
+
+    /// Number of threads.
+    pub fn thread_count(&self) -> i64 {
+        self.thread_count
+    }
+
+    /// Total CPU time spent by the process so far.
+    pub fn cpu_time(&self) -> Duration {
+        self.cpu_time
+    }
+
+    /// The process's current run state.
+    pub fn state(&self) -> ProcessState {
+        self.state
+    }
+
+    /// CPU usage percentage since `previous`, computed from the growth of
+    /// [`Self::cpu_time`] over `elapsed` wall-clock time.
+    ///
+    /// Returns `None` if `previous` isn't an earlier sample of this very
+    /// process instance (same PID *and* `start_time`) — e.g. a restart
+    /// that reused the PID, which would otherwise be compared against a
+    /// stale predecessor.
+    pub fn cpu_percent_since(&self, previous: &ProcessInfo, elapsed: Duration) -> Option<f64> {
+        if previous.pid != self.pid || previous.start_time != self.start_time {
+            return None;
+        }
+        let cpu_delta = self.cpu_time.saturating_sub(previous.cpu_time);
+        Some(100. * cpu_delta.as_secs_f64() / elapsed.as_secs_f64())
+    }
+}
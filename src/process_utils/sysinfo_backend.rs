@@ -0,0 +1,171 @@
+//! The [`ProcessBackend`] for platforms without `procfs` (FreeBSD, macOS),
+//! backed by the `sysinfo` crate.
+//!
+//! `sysinfo` doesn't expose open file descriptor counts or per-process
+//! CPU-time accounting uniformly across these platforms, so
+//! [`ProcessInfo::fd_count`] and [`ProcessInfo::cpu_time`] are always `0`
+//! here, and [`SysinfoBackend`] reports both `--cpu-threshold` and
+//! `--fd-threshold` as unsupported (see
+//! [`ProcessBackend::supports_cpu_threshold`],
+//! [`ProcessBackend::supports_fd_threshold`]) rather than silently never
+//! tripping them. [`ProcessInfo::thread_count`] is likewise always `0`.
+
+use std::{collections::HashMap, ffi::OsString, path::Path, sync::Mutex, time::Duration};
+
+use libc::{c_int, pid_t};
+use snafu::{OptionExt, Snafu};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessStatus, RefreshKind, Signal, System};
+
+use super::{
+    state_included, InvalidSignalSnafu, KillError, NotFoundSnafu, ProcessBackend, ProcessInfo,
+    ProcessMatcher, ProcessState,
+};
+
+/// `sysinfo`'s process lookups are `Option`-based rather than fallible, so
+/// this backend never actually constructs one of these.
+#[derive(Debug, Snafu)]
+pub enum FindProcessError {}
+
+/// The `sysinfo`-based [`ProcessBackend`] used on FreeBSD/macOS.
+pub struct SysinfoBackend {
+    system: Mutex<System>,
+}
+
+impl SysinfoBackend {
+    /// Creates a new backend with a freshly refreshed process list.
+    pub fn new() -> Self {
+        let system = System::new_with_specifics(
+            RefreshKind::new().with_processes(ProcessRefreshKind::everything()),
+        );
+        Self {
+            system: Mutex::new(system),
+        }
+    }
+}
+
+impl Default for SysinfoBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn process_state(status: ProcessStatus) -> ProcessState {
+    match status {
+        ProcessStatus::Run => ProcessState::Run,
+        ProcessStatus::Sleep => ProcessState::Sleep,
+        ProcessStatus::Stop => ProcessState::Stop,
+        ProcessStatus::Zombie => ProcessState::Zombie,
+        ProcessStatus::Dead => ProcessState::Dead,
+        ProcessStatus::Idle => ProcessState::Idle,
+        _ => ProcessState::Unknown,
+    }
+}
+
+fn process_info(pid: Pid, process: &sysinfo::Process) -> ProcessInfo {
+    let env: HashMap<OsString, OsString> = process
+        .environ()
+        .iter()
+        .filter_map(|entry| {
+            entry
+                .to_string_lossy()
+                .split_once('=')
+                .map(|(k, v)| (k.to_owned(), v.to_owned()))
+        })
+        .map(|(key, value)| (OsString::from(key), OsString::from(value)))
+        .collect();
+
+    ProcessInfo {
+        pid: pid.as_u32() as pid_t,
+        rss: process.memory(),
+        env,
+        start_time: process.start_time(),
+        command: process.name().to_string_lossy().into_owned(),
+        exe: process.exe().map(Path::to_path_buf).unwrap_or_default(),
+        cmdline: process
+            .cmd()
+            .iter()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect(),
+        vsize: process.virtual_memory(),
+        fd_count: 0,
+        thread_count: 0,
+        cpu_time: Duration::ZERO,
+        state: process_state(process.status()),
+    }
+}
+
+fn matches(info: &ProcessInfo, matcher: &ProcessMatcher) -> bool {
+    match matcher {
+        ProcessMatcher::Comm(name) => &info.command == name,
+        ProcessMatcher::Exe(name) => {
+            info.exe.file_name().and_then(std::ffi::OsStr::to_str) == Some(name.as_str())
+        }
+        ProcessMatcher::Cmdline(regex) => regex.is_match(&info.cmdline.join(" ")),
+    }
+}
+
+impl ProcessBackend for SysinfoBackend {
+    fn find_processes(
+        &self,
+        matcher: &ProcessMatcher,
+        include_states: &[ProcessState],
+    ) -> Result<Vec<ProcessInfo>, super::FindProcessError> {
+        let mut system = self.system.lock().expect("sysinfo mutex poisoned");
+        system.refresh_processes();
+
+        Ok(system
+            .processes()
+            .iter()
+            .map(|(pid, process)| process_info(*pid, process))
+            .filter(|info| matches(info, matcher) && state_included(info.state, include_states))
+            .inspect(|info| log::trace!("Found process #{} (state: {})", info.pid, info.state))
+            .collect())
+    }
+
+    fn process_by_pid(&self, pid: pid_t) -> Result<Option<ProcessInfo>, super::FindProcessError> {
+        let sysinfo_pid = Pid::from_u32(pid as u32);
+        let mut system = self.system.lock().expect("sysinfo mutex poisoned");
+        system.refresh_processes();
+        Ok(system
+            .process(sysinfo_pid)
+            .map(|process| process_info(sysinfo_pid, process)))
+    }
+
+    fn send_signal(&self, pid: pid_t, signal: c_int) -> Result<(), KillError> {
+        let sysinfo_pid = Pid::from_u32(pid as u32);
+        let mut system = self.system.lock().expect("sysinfo mutex poisoned");
+        system.refresh_processes();
+
+        let process = system.process(sysinfo_pid).context(NotFoundSnafu { pid })?;
+
+        // Signal `0` is a non-destructive liveness probe (see
+        // `ProcessInfo::has_stopped`): it must not actually deliver
+        // anything, just report whether the process still exists.
+        if signal == 0 {
+            return Ok(());
+        }
+
+        let sysinfo_signal = signal_from_libc(signal).context(InvalidSignalSnafu { signal })?;
+        if process.kill_with(sysinfo_signal).unwrap_or(false) {
+            Ok(())
+        } else {
+            NotFoundSnafu { pid }.fail()
+        }
+    }
+
+    fn supports_cpu_threshold(&self) -> bool {
+        false
+    }
+
+    fn supports_fd_threshold(&self) -> bool {
+        false
+    }
+}
+
+fn signal_from_libc(signal: c_int) -> Option<Signal> {
+    match signal {
+        libc::SIGTERM => Some(Signal::Term),
+        libc::SIGKILL => Some(Signal::Kill),
+        _ => None,
+    }
+}